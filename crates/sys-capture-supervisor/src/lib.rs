@@ -0,0 +1,402 @@
+//! pidfd-based capture-process supervisor with signal fallback.
+//!
+//! Owns a spawned child and notifies listeners the instant it exits,
+//! instead of a background `on_change` callback calling `std::process::exit`
+//! - which can't cleanly tear down a spawned Qt overlay and races with
+//! reaping. Modeled on async-process's waitable backend: on Linux >= 5.3 we
+//! obtain a pidfd for the child (`pidfd_open`) and block on it becoming
+//! readable, which means "child exited" with no zombie races and no global
+//! `SIGCHLD` handler. On older kernels or non-Linux platforms we fall back
+//! to a dedicated reaper thread blocked in `Child::wait()`.
+
+use std::io::Write;
+use std::process::{Child, ChildStdin, ChildStdout, Command};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+
+/// Signal to send for a graceful stop request. Borrowed from watchexec's
+/// `--stop-signal`/`--stop-timeout` model: ask nicely first, only escalate
+/// to a hard kill if the process ignores the request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopSignal {
+    /// `SIGTERM` on Unix. On Windows every variant is a hard
+    /// `TerminateProcess`; there is no soft stop there.
+    Term,
+    /// `SIGINT` on Unix; a hard `TerminateProcess` on Windows.
+    Interrupt,
+    /// `SIGHUP` on Unix; a hard `TerminateProcess` on Windows.
+    Hangup,
+}
+
+#[cfg(unix)]
+impl StopSignal {
+    fn as_libc(self) -> i32 {
+        match self {
+            StopSignal::Term => libc::SIGTERM,
+            StopSignal::Interrupt => libc::SIGINT,
+            StopSignal::Hangup => libc::SIGHUP,
+        }
+    }
+}
+
+/// How to ask a supervised process to stop before escalating to a hard kill.
+#[derive(Debug, Clone, Copy)]
+pub struct TerminateConfig {
+    pub stop_signal: StopSignal,
+    pub stop_timeout: Duration,
+}
+
+impl Default for TerminateConfig {
+    fn default() -> Self {
+        Self {
+            stop_signal: StopSignal::Term,
+            stop_timeout: Duration::from_secs(3),
+        }
+    }
+}
+
+/// A supervised child process.
+pub struct Supervisor {
+    pid: u32,
+    stdout: Mutex<Option<ChildStdout>>,
+    stdin: Mutex<Option<ChildStdin>>,
+    exit_code: Arc<Mutex<Option<i32>>>,
+    exit_signal: Arc<(Mutex<bool>, Condvar)>,
+    reaper: Option<JoinHandle<()>>,
+    #[cfg(target_os = "linux")]
+    pidfd: Option<std::os::unix::io::RawFd>,
+    /// The child's process HANDLE, captured before it's moved into the
+    /// reaper thread. Stored as `isize` (a HANDLE's underlying
+    /// representation) rather than the non-`Send` `RawHandle` pointer type,
+    /// mirroring `pidfd`'s use of a plain `RawFd` above. Lets `terminate`/
+    /// `kill` call `TerminateProcess` directly instead of shelling out to
+    /// `taskkill`, which re-resolves the PID and could hit the wrong
+    /// process if it's been reused since.
+    #[cfg(windows)]
+    handle: isize,
+}
+
+impl Supervisor {
+    /// Spawn `cmd` and start supervising it.
+    pub fn spawn(mut cmd: Command) -> Result<Self> {
+        let mut child: Child = cmd.spawn().context("Failed to spawn supervised process")?;
+        let pid = child.id();
+        let stdout = child.stdout.take();
+        let stdin = child.stdin.take();
+
+        #[cfg(target_os = "linux")]
+        let pidfd = linux::pidfd_open(pid);
+
+        #[cfg(windows)]
+        let handle = {
+            use std::os::windows::io::AsRawHandle;
+            child.as_raw_handle() as isize
+        };
+
+        let exit_code: Arc<Mutex<Option<i32>>> = Arc::new(Mutex::new(None));
+        let exit_signal = Arc::new((Mutex::new(false), Condvar::new()));
+
+        #[cfg(target_os = "linux")]
+        let pidfd_for_reaper = pidfd;
+
+        let reaper = {
+            let exit_code = exit_code.clone();
+            let exit_signal = exit_signal.clone();
+            thread::spawn(move || {
+                #[cfg(target_os = "linux")]
+                if let Some(fd) = pidfd_for_reaper {
+                    // Becoming readable means the child has exited; the
+                    // blocking `wait()` right after this is then instant.
+                    linux::wait_on_pidfd(fd);
+                }
+
+                let code = child.wait().ok().and_then(|s| s.code()).unwrap_or(-1);
+
+                *exit_code.lock().unwrap() = Some(code);
+                let (exited, cvar) = &*exit_signal;
+                *exited.lock().unwrap() = true;
+                cvar.notify_all();
+            })
+        };
+
+        Ok(Self {
+            pid,
+            stdout: Mutex::new(stdout),
+            stdin: Mutex::new(stdin),
+            exit_code,
+            exit_signal,
+            reaper: Some(reaper),
+            #[cfg(target_os = "linux")]
+            pidfd,
+            #[cfg(windows)]
+            handle,
+        })
+    }
+
+    /// The supervised process's id.
+    pub fn id(&self) -> u32 {
+        self.pid
+    }
+
+    /// Take ownership of the child's stdout, for IPC with it.
+    pub fn take_stdout(&self) -> Option<ChildStdout> {
+        self.stdout.lock().unwrap().take()
+    }
+
+    /// Write `line` plus a newline to the child's stdin, for sending it a
+    /// command. Returns an error if the child has no piped stdin (it wasn't
+    /// requested at spawn time) or the write fails (e.g. the child already
+    /// exited and closed its end).
+    pub fn send_line(&self, line: &str) -> Result<()> {
+        let mut guard = self.stdin.lock().unwrap();
+        let stdin = guard.as_mut().context("Child has no piped stdin")?;
+        writeln!(stdin, "{line}").context("Failed to write to child's stdin")
+    }
+
+    /// Non-blocking check: `Some(exit_code)` if the child has already
+    /// exited, `None` if it's still running.
+    pub fn try_wait(&self) -> Option<i32> {
+        *self.exit_code.lock().unwrap()
+    }
+
+    /// Block until the child exits, returning its exit code (-1 if it
+    /// couldn't be determined, e.g. killed by a signal).
+    pub fn wait(&self) -> i32 {
+        let (exited, cvar) = &*self.exit_signal;
+        let mut guard = exited.lock().unwrap();
+        while !*guard {
+            guard = cvar.wait(guard).unwrap();
+        }
+        self.exit_code.lock().unwrap().unwrap_or(-1)
+    }
+
+    /// Register a callback that runs once, on a background thread, the
+    /// instant the child exits.
+    pub fn on_exit<F>(&self, cb: F)
+    where
+        F: FnOnce(i32) + Send + 'static,
+    {
+        let exit_signal = self.exit_signal.clone();
+        let exit_code = self.exit_code.clone();
+        thread::spawn(move || {
+            let (exited, cvar) = &*exit_signal;
+            let mut guard = exited.lock().unwrap();
+            while !*guard {
+                guard = cvar.wait(guard).unwrap();
+            }
+            cb(exit_code.lock().unwrap().unwrap_or(-1));
+        });
+    }
+
+    /// Ask the child to stop gracefully per `config`, escalating to
+    /// [`Supervisor::kill`] if it's still alive after `config.stop_timeout`.
+    /// This gives Qt a chance to restore the cursor, un-freeze the screen,
+    /// and flush its window before it disappears, instead of a bare
+    /// `process::exit` that can't tear anything down cleanly.
+    pub fn terminate(&self, config: &TerminateConfig) {
+        #[cfg(target_os = "linux")]
+        {
+            match self.pidfd {
+                Some(fd) => linux::pidfd_send_signal(fd, config.stop_signal.as_libc()),
+                None => unsafe {
+                    libc::kill(self.pid as libc::pid_t, config.stop_signal.as_libc());
+                },
+            }
+        }
+        #[cfg(all(unix, not(target_os = "linux")))]
+        unsafe {
+            libc::kill(self.pid as libc::pid_t, config.stop_signal.as_libc());
+        }
+        #[cfg(windows)]
+        {
+            // Windows has no real analogue to a catchable SIGTERM - the
+            // closest primitive that works without a console/message loop
+            // on the target process is `TerminateProcess`, so `terminate`
+            // and `kill` converge here. `StopSignal` is kept for API
+            // symmetry with Unix; all three variants behave the same way.
+            windows::terminate_process(self.handle);
+        }
+
+        let deadline = Instant::now() + config.stop_timeout;
+        while Instant::now() < deadline {
+            if self.try_wait().is_some() {
+                return;
+            }
+            thread::sleep(Duration::from_millis(50));
+        }
+
+        if self.try_wait().is_none() {
+            self.kill();
+        }
+    }
+
+    /// Forcefully terminate the child.
+    ///
+    /// On Linux, uses `pidfd_send_signal` so the signal targets the exact
+    /// process we spawned even if its PID has since been reused - unlike
+    /// signalling by raw PID, this can't hit the wrong process.
+    pub fn kill(&self) {
+        #[cfg(target_os = "linux")]
+        {
+            if let Some(fd) = self.pidfd {
+                linux::pidfd_send_signal(fd, libc::SIGKILL);
+                return;
+            }
+        }
+        #[cfg(unix)]
+        unsafe {
+            libc::kill(self.pid as libc::pid_t, libc::SIGKILL);
+        }
+        #[cfg(windows)]
+        windows::terminate_process(self.handle);
+    }
+}
+
+impl Drop for Supervisor {
+    fn drop(&mut self) {
+        #[cfg(target_os = "linux")]
+        if let Some(fd) = self.pidfd.take() {
+            unsafe {
+                libc::close(fd);
+            }
+        }
+        if let Some(reaper) = self.reaper.take() {
+            let _ = reaper.join();
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use std::os::unix::io::RawFd;
+
+    // Not yet exposed by all libc versions we support, so call through the
+    // raw syscall numbers directly (stable on x86_64/aarch64 since 5.3/5.1).
+    const SYS_PIDFD_SEND_SIGNAL: i64 = 424;
+    const SYS_PIDFD_OPEN: i64 = 434;
+
+    /// Obtain a pidfd for `pid`. Returns `None` on kernels older than 5.3,
+    /// or if the syscall is blocked (e.g. a restrictive seccomp profile) -
+    /// the caller then relies solely on the `Child::wait()` reaper thread.
+    pub fn pidfd_open(pid: u32) -> Option<RawFd> {
+        let fd = unsafe { libc::syscall(SYS_PIDFD_OPEN, pid as libc::pid_t, 0) };
+        if fd < 0 {
+            None
+        } else {
+            Some(fd as RawFd)
+        }
+    }
+
+    pub fn pidfd_send_signal(fd: RawFd, sig: i32) {
+        unsafe {
+            libc::syscall(SYS_PIDFD_SEND_SIGNAL, fd, sig, std::ptr::null::<u8>(), 0);
+        }
+    }
+
+    /// Block until `fd` is readable, i.e. the process it refers to exited.
+    pub fn wait_on_pidfd(fd: RawFd) {
+        let mut pfd = libc::pollfd {
+            fd,
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        loop {
+            let ret = unsafe { libc::poll(&mut pfd, 1, -1) };
+            if ret >= 0 {
+                return;
+            }
+            let err = std::io::Error::last_os_error();
+            if err.kind() != std::io::ErrorKind::Interrupted {
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(windows)]
+mod windows {
+    use windows_sys::Win32::Foundation::HANDLE;
+    use windows_sys::Win32::System::Threading::TerminateProcess;
+
+    /// Forcefully terminate the process owning `handle` - a direct
+    /// `TerminateProcess` call through the handle we already hold, rather
+    /// than a `taskkill` subprocess that re-looks the target up by PID.
+    pub fn terminate_process(handle: isize) {
+        // SAFETY: `handle` was obtained from `Child::as_raw_handle` and
+        // stays valid for at least as long as `Supervisor` is alive - the
+        // `Child` it came from is only dropped after `child.wait()`
+        // returns in the reaper thread.
+        unsafe {
+            TerminateProcess(handle as HANDLE, 1);
+        }
+    }
+}
+
+// `true`/`sh`/`sleep` aren't available on Windows, so these tests only
+// build on Unix.
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    fn sleep_cmd(secs: u64) -> Command {
+        let mut cmd = Command::new("sleep");
+        cmd.arg(secs.to_string());
+        cmd
+    }
+
+    #[test]
+    fn test_wait_returns_exit_code() {
+        let sv = Supervisor::spawn(Command::new("true")).unwrap();
+        assert_eq!(sv.wait(), 0);
+    }
+
+    #[test]
+    fn test_try_wait_none_then_some() {
+        let sv = Supervisor::spawn(sleep_cmd(1)).unwrap();
+        assert_eq!(sv.try_wait(), None);
+        assert_eq!(sv.wait(), 0);
+        assert_eq!(sv.try_wait(), Some(0));
+    }
+
+    #[test]
+    fn test_on_exit_fires() {
+        let sv = Supervisor::spawn(Command::new("true")).unwrap();
+        let (tx, rx) = mpsc::channel();
+        sv.on_exit(move |code| {
+            let _ = tx.send(code);
+        });
+        assert_eq!(rx.recv_timeout(Duration::from_secs(5)).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_kill_terminates_long_running_child() {
+        let sv = Supervisor::spawn(sleep_cmd(30)).unwrap();
+        sv.kill();
+        assert_ne!(sv.wait(), 0);
+    }
+
+    #[test]
+    fn test_terminate_escalates_to_kill_on_timeout() {
+        // A bare `sleep` exits on SIGTERM, which would never reach the
+        // escalation. Run it under a shell that ignores SIGTERM (an ignored
+        // disposition survives `exec`), so only the SIGKILL sent after
+        // `stop_timeout` can stop it.
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", "trap '' TERM; exec sleep 30"]);
+        let sv = Supervisor::spawn(cmd).unwrap();
+        // Give the shell time to install the trap before signalling.
+        thread::sleep(Duration::from_millis(200));
+        let started = Instant::now();
+        sv.terminate(&TerminateConfig {
+            stop_signal: StopSignal::Term,
+            stop_timeout: Duration::from_millis(200),
+        });
+        assert!(started.elapsed() >= Duration::from_millis(200));
+        // Killed by a signal, so there's no exit code.
+        assert_eq!(sv.wait(), -1);
+    }
+}
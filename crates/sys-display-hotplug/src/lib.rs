@@ -3,7 +3,18 @@
 //! Monitors for HDMI/VGA cable plug/unplug events during capture.
 //! When topology changes, triggers a callback to kill the capture process.
 //! This prevents ghost freezes and jumps to primary screen.
+//!
+//! Notification is event-driven on each platform (netlink uevents on
+//! Linux, `CGDisplayRegisterReconfigurationCallback` on macOS, a
+//! message-only window on Windows), so there is no background subprocess
+//! and latency is bounded by the OS delivering the event rather than a
+//! fixed poll interval. If the native mechanism can't be set up (old
+//! kernel, sandboxed environment, missing permissions), Linux falls back
+//! to polling a sysfs display fingerprint so capture still gets some
+//! protection.
 
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::thread::{self, JoinHandle};
@@ -17,10 +28,10 @@ pub struct DisplayWatcher {
 
 impl DisplayWatcher {
     /// Start watching for display topology changes in a background thread.
-    /// 
+    ///
     /// The `on_change` callback will be called if monitors are added/removed.
     /// After calling the callback, the watcher thread exits.
-    /// 
+    ///
     /// # Example
     /// ```ignore
     /// let watcher = DisplayWatcher::start(|| {
@@ -38,15 +49,7 @@ impl DisplayWatcher {
         let running_clone = running.clone();
 
         let handle = thread::spawn(move || {
-            let mut monitor = DisplayMonitor::new();
-            
-            while running_clone.load(Ordering::Relaxed) {
-                if monitor.check() {
-                    on_change();
-                    break;
-                }
-                thread::sleep(Duration::from_millis(300));
-            }
+            run_watch_loop(running_clone, on_change);
         });
 
         Self {
@@ -71,16 +74,436 @@ impl Drop for DisplayWatcher {
     }
 }
 
-/// Internal display monitor with debouncing
+/// Dispatch to the platform-specific event-driven watcher, falling back to
+/// the sysfs-fingerprint poll loop where a native mechanism isn't available.
+fn run_watch_loop<F>(running: Arc<AtomicBool>, on_change: F)
+where
+    F: FnOnce() + Send + 'static,
+{
+    #[cfg(target_os = "linux")]
+    {
+        match linux::open_uevent_socket() {
+            Some(fd) => linux::watch(fd, running, on_change),
+            None => {
+                eprintln!("[sys-display-hotplug] netlink unavailable, falling back to sysfs polling");
+                poll_fallback(running, on_change);
+            }
+        }
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        macos::watch(running, on_change);
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        windows::watch(running, on_change);
+    }
+}
+
+/// The old poll loop, kept as a fallback for platforms/environments where
+/// the native notification mechanism isn't available.
+#[cfg(target_os = "linux")]
+fn poll_fallback<F>(running: Arc<AtomicBool>, on_change: F)
+where
+    F: FnOnce() + Send + 'static,
+{
+    let mut monitor = DisplayMonitor::new();
+
+    while running.load(Ordering::Relaxed) {
+        if monitor.check() {
+            on_change();
+            break;
+        }
+        thread::sleep(Duration::from_millis(300));
+    }
+}
+
+// ========== Linux: netlink kobject uevents ==========
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use std::os::unix::io::RawFd;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
+    const NETLINK_KOBJECT_UEVENT: i32 = 15;
+    /// The "kernel" multicast group - raw uevents straight from the kernel,
+    /// framed as plain NUL-separated `KEY=VALUE` lines. Group 2 ("udev") is
+    /// re-broadcast by udevd with a binary `libudev` header (magic number,
+    /// `properties_off`, hash fields, ...) prefixed before those same
+    /// lines, which `is_drm_hotplug`'s exact-match text parsing below can't
+    /// see through - binding it would silently never match.
+    const KERNEL_MONITOR_GROUP: u32 = 1;
+    /// Coalesce a burst of uevents from a single physical plug/unplug
+    /// (a `change` event is often followed by connector-specific events)
+    /// into a single callback invocation.
+    const DEBOUNCE: Duration = Duration::from_millis(150);
+
+    #[repr(C)]
+    struct SockaddrNl {
+        nl_family: libc::sa_family_t,
+        nl_pad: libc::c_ushort,
+        nl_pid: u32,
+        nl_groups: u32,
+    }
+
+    /// Open and bind a netlink socket subscribed to the kernel's raw kobject
+    /// hotplug events (not udevd's re-broadcast). Returns `None` if netlink can't be opened or bound (e.g. no
+    /// `CAP_NET_ADMIN` in a sandboxed environment), so the caller can fall
+    /// back to sysfs polling.
+    pub fn open_uevent_socket() -> Option<RawFd> {
+        unsafe {
+            let fd = libc::socket(libc::AF_NETLINK, libc::SOCK_RAW, NETLINK_KOBJECT_UEVENT);
+            if fd < 0 {
+                return None;
+            }
+
+            let addr = SockaddrNl {
+                nl_family: libc::AF_NETLINK as libc::sa_family_t,
+                nl_pad: 0,
+                nl_pid: 0, // let the kernel assign our port id
+                nl_groups: KERNEL_MONITOR_GROUP,
+            };
+
+            let bound = libc::bind(
+                fd,
+                &addr as *const SockaddrNl as *const libc::sockaddr,
+                std::mem::size_of::<SockaddrNl>() as libc::socklen_t,
+            );
+            if bound < 0 {
+                libc::close(fd);
+                return None;
+            }
+
+            // Bound recv calls so `watch` can notice `running` flipping to
+            // false instead of blocking in `recv` indefinitely.
+            let timeout = libc::timeval {
+                tv_sec: 1,
+                tv_usec: 0,
+            };
+            libc::setsockopt(
+                fd,
+                libc::SOL_SOCKET,
+                libc::SO_RCVTIMEO,
+                &timeout as *const _ as *const libc::c_void,
+                std::mem::size_of::<libc::timeval>() as libc::socklen_t,
+            );
+
+            Some(fd)
+        }
+    }
+
+    pub fn watch<F>(fd: RawFd, running: Arc<AtomicBool>, on_change: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        let mut buf = [0u8; 4096];
+
+        while running.load(Ordering::Relaxed) {
+            let n =
+                unsafe { libc::recv(fd, buf.as_mut_ptr() as *mut libc::c_void, buf.len(), 0) };
+            if n <= 0 {
+                continue; // timed out or interrupted - loop back and recheck `running`
+            }
+
+            if !is_drm_hotplug(&buf[..n as usize]) {
+                continue;
+            }
+
+            // Swallow the rest of this burst before acting.
+            thread::sleep(DEBOUNCE);
+            loop {
+                let n = unsafe {
+                    libc::recv(
+                        fd,
+                        buf.as_mut_ptr() as *mut libc::c_void,
+                        buf.len(),
+                        libc::MSG_DONTWAIT,
+                    )
+                };
+                if n <= 0 {
+                    break;
+                }
+            }
+
+            on_change();
+            break;
+        }
+
+        unsafe {
+            libc::close(fd);
+        }
+    }
+
+    /// A uevent datagram is a sequence of NUL-separated `KEY=VALUE` lines.
+    /// Only DRM hotplug matters here: `SUBSYSTEM=drm`, an action of
+    /// `change`/`add`/`remove`, and `HOTPLUG=1`.
+    fn is_drm_hotplug(raw: &[u8]) -> bool {
+        let msg = String::from_utf8_lossy(raw);
+
+        let mut is_drm = false;
+        let mut is_hotplug_action = false;
+        let mut has_hotplug_flag = false;
+
+        for line in msg.split('\0') {
+            if line == "SUBSYSTEM=drm" {
+                is_drm = true;
+            } else if line == "HOTPLUG=1" {
+                has_hotplug_flag = true;
+            } else if let Some(action) = line.strip_prefix("ACTION=") {
+                is_hotplug_action = matches!(action, "change" | "add" | "remove");
+            }
+        }
+
+        is_drm && is_hotplug_action && has_hotplug_flag
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        /// A captured raw (group 1) kernel uevent datagram for a DRM
+        /// connector hotplug - NUL-separated `KEY=VALUE` lines, no binary
+        /// header, matching what `KERNEL_MONITOR_GROUP` actually delivers.
+        fn raw_drm_hotplug_datagram() -> Vec<u8> {
+            [
+                "change@/devices/pci0000:00/0000:00:02.0/drm/card0",
+                "ACTION=change",
+                "DEVPATH=/devices/pci0000:00/0000:00:02.0/drm/card0",
+                "SUBSYSTEM=drm",
+                "HOTPLUG=1",
+            ]
+            .join("\0")
+            .into_bytes()
+        }
+
+        #[test]
+        fn test_is_drm_hotplug_matches_raw_kernel_datagram() {
+            assert!(is_drm_hotplug(&raw_drm_hotplug_datagram()));
+        }
+
+        #[test]
+        fn test_is_drm_hotplug_rejects_non_drm_subsystem() {
+            let msg = ["ACTION=change", "SUBSYSTEM=usb", "HOTPLUG=1"].join("\0");
+            assert!(!is_drm_hotplug(msg.as_bytes()));
+        }
+
+        #[test]
+        fn test_is_drm_hotplug_rejects_missing_hotplug_flag() {
+            let msg = ["ACTION=change", "SUBSYSTEM=drm"].join("\0");
+            assert!(!is_drm_hotplug(msg.as_bytes()));
+        }
+
+        #[test]
+        fn test_is_drm_hotplug_rejects_unrelated_action() {
+            let msg = ["ACTION=bind", "SUBSYSTEM=drm", "HOTPLUG=1"].join("\0");
+            assert!(!is_drm_hotplug(msg.as_bytes()));
+        }
+    }
+}
+
+// ========== macOS: CGDisplayRegisterReconfigurationCallback ==========
+
+#[cfg(target_os = "macos")]
+mod macos {
+    use std::os::raw::c_void;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
+    type CgDirectDisplayId = u32;
+    type CgDisplayChangeSummaryFlags = u32;
+    type CgDisplayReconfigurationCallback =
+        extern "C" fn(CgDirectDisplayId, CgDisplayChangeSummaryFlags, *mut c_void);
+
+    const K_CG_DISPLAY_ADD_FLAG: u32 = 1 << 1;
+    const K_CG_DISPLAY_REMOVE_FLAG: u32 = 1 << 2;
+    const K_CG_DISPLAY_DESKTOP_SHAPE_CHANGED_FLAG: u32 = 1 << 12;
+    const INTERESTING_FLAGS: u32 =
+        K_CG_DISPLAY_ADD_FLAG | K_CG_DISPLAY_REMOVE_FLAG | K_CG_DISPLAY_DESKTOP_SHAPE_CHANGED_FLAG;
+
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        fn CGDisplayRegisterReconfigurationCallback(
+            callback: CgDisplayReconfigurationCallback,
+            user_info: *mut c_void,
+        ) -> i32;
+        fn CGDisplayRemoveReconfigurationCallback(
+            callback: CgDisplayReconfigurationCallback,
+            user_info: *mut c_void,
+        ) -> i32;
+    }
+
+    extern "C" fn reconfiguration_callback(
+        _display: CgDirectDisplayId,
+        flags: CgDisplayChangeSummaryFlags,
+        user_info: *mut c_void,
+    ) {
+        if flags & INTERESTING_FLAGS == 0 {
+            return;
+        }
+        // SAFETY: `user_info` is the `*const AtomicBool` we registered below,
+        // kept alive for as long as the callback is registered.
+        let changed = unsafe { &*(user_info as *const AtomicBool) };
+        changed.store(true, Ordering::SeqCst);
+    }
+
+    pub fn watch<F>(running: Arc<AtomicBool>, on_change: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        let changed = Box::new(AtomicBool::new(false));
+        let changed_ptr = Box::into_raw(changed);
+
+        unsafe {
+            CGDisplayRegisterReconfigurationCallback(
+                reconfiguration_callback,
+                changed_ptr as *mut c_void,
+            );
+        }
+
+        // CGDisplayRegisterReconfigurationCallback delivers on the
+        // registering thread's run loop; poll the flag it sets rather than
+        // spinning up a full CFRunLoop here.
+        while running.load(Ordering::Relaxed) {
+            if unsafe { &*changed_ptr }.load(Ordering::SeqCst) {
+                break;
+            }
+            thread::sleep(Duration::from_millis(100));
+        }
+
+        let fired = unsafe { &*changed_ptr }.load(Ordering::SeqCst);
+
+        unsafe {
+            CGDisplayRemoveReconfigurationCallback(
+                reconfiguration_callback,
+                changed_ptr as *mut c_void,
+            );
+            drop(Box::from_raw(changed_ptr));
+        }
+
+        if fired {
+            on_change();
+        }
+    }
+}
+
+// ========== Windows: message-only window + WM_DISPLAYCHANGE ==========
+
+#[cfg(target_os = "windows")]
+mod windows {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use windows_sys::Win32::Foundation::{HWND, LPARAM, LRESULT, WPARAM};
+    use windows_sys::Win32::UI::WindowsAndMessaging::{
+        CreateWindowExW, DefWindowProcW, DestroyWindow, DispatchMessageW, GetWindowLongPtrW,
+        PeekMessageW, RegisterClassExW, SetWindowLongPtrW, TranslateMessage, GWLP_USERDATA,
+        HWND_MESSAGE, MSG, PM_REMOVE, WM_DISPLAYCHANGE, WNDCLASSEXW,
+    };
+
+    unsafe extern "system" fn wnd_proc(
+        hwnd: HWND,
+        msg: u32,
+        wparam: WPARAM,
+        lparam: LPARAM,
+    ) -> LRESULT {
+        if msg == WM_DISPLAYCHANGE {
+            let changed = GetWindowLongPtrW(hwnd, GWLP_USERDATA) as *const AtomicBool;
+            if !changed.is_null() {
+                (*changed).store(true, Ordering::SeqCst);
+            }
+        }
+        DefWindowProcW(hwnd, msg, wparam, lparam)
+    }
+
+    pub fn watch<F>(running: Arc<AtomicBool>, on_change: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        let changed = Box::new(AtomicBool::new(false));
+        let changed_ptr = Box::into_raw(changed);
+
+        unsafe {
+            let class_name: Vec<u16> = "QtCapDisplayWatcher\0".encode_utf16().collect();
+
+            let wc = WNDCLASSEXW {
+                cbSize: std::mem::size_of::<WNDCLASSEXW>() as u32,
+                lpfnWndProc: Some(wnd_proc),
+                lpszClassName: class_name.as_ptr(),
+                ..std::mem::zeroed()
+            };
+            RegisterClassExW(&wc);
+
+            let hwnd = CreateWindowExW(
+                0,
+                class_name.as_ptr(),
+                std::ptr::null(),
+                0,
+                0,
+                0,
+                0,
+                0,
+                HWND_MESSAGE,
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+                std::ptr::null(),
+            );
+            SetWindowLongPtrW(hwnd, GWLP_USERDATA, changed_ptr as isize);
+
+            let mut msg: MSG = std::mem::zeroed();
+            while running.load(Ordering::Relaxed) {
+                while PeekMessageW(&mut msg, hwnd, 0, 0, PM_REMOVE) != 0 {
+                    TranslateMessage(&msg);
+                    DispatchMessageW(&msg);
+                }
+                if (*changed_ptr).load(Ordering::SeqCst) {
+                    break;
+                }
+                std::thread::sleep(Duration::from_millis(50));
+            }
+
+            let fired = (*changed_ptr).load(Ordering::SeqCst);
+            DestroyWindow(hwnd);
+            drop(Box::from_raw(changed_ptr));
+
+            if fired {
+                on_change();
+            }
+        }
+    }
+}
+
+/// Internal display monitor with debouncing, used as the sysfs-polling
+/// fallback on Linux when netlink can't be opened. Linux-only: macOS and
+/// Windows get their topology notifications from
+/// `CGDisplayRegisterReconfigurationCallback`/`WM_DISPLAYCHANGE`
+/// (see the `macos`/`windows` modules above) and never go through
+/// `run_watch_loop`'s poll-fallback branch, so there's no fingerprint to
+/// compute there.
+///
+/// Tracks a fingerprint - a hash of the sorted per-connector/display
+/// descriptors (name, connection status, resolution) - rather than a bare
+/// monitor count. A count alone misses topology rearrangements that don't
+/// change the number of displays, e.g. swapping which port a monitor is
+/// plugged into or a mode change on the same physical set of displays.
+#[cfg(target_os = "linux")]
 pub struct DisplayMonitor {
-    last_count: i32,
+    last_fingerprint: u64,
     last_check: Instant,
 }
 
+#[cfg(target_os = "linux")]
 impl DisplayMonitor {
     pub fn new() -> Self {
         Self {
-            last_count: Self::get_monitor_count(),
+            last_fingerprint: Self::get_fingerprint(),
             last_check: Instant::now(),
         }
     }
@@ -94,93 +517,69 @@ impl DisplayMonitor {
         }
         self.last_check = Instant::now();
 
-        let current = Self::get_monitor_count();
-        if current != self.last_count {
+        let current = Self::get_fingerprint();
+        if current != self.last_fingerprint {
             // Debounce: confirm after 500ms
             thread::sleep(Duration::from_millis(500));
-            let confirmed = Self::get_monitor_count();
+            let confirmed = Self::get_fingerprint();
 
-            if confirmed != self.last_count {
-                self.last_count = confirmed;
+            if confirmed != self.last_fingerprint {
+                self.last_fingerprint = confirmed;
                 return true;
             }
         }
         false
     }
 
+    /// Hash a sorted list of per-display descriptor strings into a single
+    /// fingerprint. Sorting first means the fingerprint is independent of
+    /// whatever order the OS happens to enumerate displays in.
+    fn hash_descriptors(mut descriptors: Vec<String>) -> u64 {
+        descriptors.sort();
+        let mut hasher = DefaultHasher::new();
+        descriptors.hash(&mut hasher);
+        hasher.finish()
+    }
+
     // ========== Linux ==========
-    
+
     #[cfg(target_os = "linux")]
-    fn get_monitor_count() -> i32 {
+    fn get_fingerprint() -> u64 {
         // Read from SysFS - extremely fast, no subprocess
-        if let Ok(entries) = std::fs::read_dir("/sys/class/drm") {
-            let count = entries
-                .filter_map(|e| e.ok())
-                .filter(|e| {
-                    let name = e.file_name().to_string_lossy().into_owned();
-                    // Only check connector entries like "card0-HDMI-A-1"
-                    if !name.starts_with("card") || !name.contains('-') {
-                        return false;
-                    }
-                    // Check if connected
-                    let status_path = e.path().join("status");
-                    std::fs::read_to_string(status_path)
-                        .map(|s| s.trim() == "connected")
-                        .unwrap_or(false)
-                })
-                .count();
-            if count > 0 {
-                return count as i32;
-            }
-        }
-        1 // Fallback
-    }
+        let Ok(entries) = std::fs::read_dir("/sys/class/drm") else {
+            return 0;
+        };
 
-    // ========== macOS ==========
-    
-    #[cfg(target_os = "macos")]
-    fn get_monitor_count() -> i32 {
-        // Use IOKit for fast enumeration
-        // Fallback to system_profiler if IOKit unavailable
-        use std::process::Command;
-        let out = Command::new("system_profiler")
-            .arg("SPDisplaysDataType")
-            .output();
-        if let Ok(o) = out {
-            String::from_utf8_lossy(&o.stdout)
-                .matches("Resolution:")
-                .count() as i32
-        } else {
-            1
-        }
-    }
-
-    // ========== Windows ==========
-    
-    #[cfg(target_os = "windows")]
-    fn get_monitor_count() -> i32 {
-        // Use EnumDisplayDevices or GetSystemMetrics
-        // Placeholder - Windows display changes are rare during capture
-        use std::process::Command;
-        // PowerShell one-liner to count monitors
-        let out = Command::new("powershell")
-            .args([
-                "-NoProfile",
-                "-Command",
-                "(Get-CimInstance -ClassName Win32_DesktopMonitor | Measure-Object).Count",
-            ])
-            .output();
-        if let Ok(o) = out {
-            String::from_utf8_lossy(&o.stdout)
-                .trim()
-                .parse()
-                .unwrap_or(1)
-        } else {
-            1
-        }
+        let descriptors: Vec<String> = entries
+            .filter_map(|e| e.ok())
+            .filter_map(|e| {
+                let name = e.file_name().to_string_lossy().into_owned();
+                // Only check connector entries like "card0-HDMI-A-1"
+                if !name.starts_with("card") || !name.contains('-') {
+                    return None;
+                }
+                let status = std::fs::read_to_string(e.path().join("status"))
+                    .unwrap_or_default()
+                    .trim()
+                    .to_string();
+                if status != "connected" {
+                    return None;
+                }
+                // `modes` lists the connector's supported modes, current
+                // first - its first line doubles as the active resolution.
+                let mode = std::fs::read_to_string(e.path().join("modes"))
+                    .ok()
+                    .and_then(|s| s.lines().next().map(str::to_string))
+                    .unwrap_or_default();
+                Some(format!("{name}:{status}:{mode}"))
+            })
+            .collect();
+
+        Self::hash_descriptors(descriptors)
     }
 }
 
+#[cfg(target_os = "linux")]
 impl Default for DisplayMonitor {
     fn default() -> Self {
         Self::new()
@@ -192,9 +591,11 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_monitor_count_nonzero() {
-        let count = DisplayMonitor::get_monitor_count();
-        assert!(count >= 1, "Should detect at least one display");
+    #[cfg(target_os = "linux")]
+    fn test_fingerprint_stable_across_calls() {
+        // With no actual hardware change between the two calls, the
+        // fingerprint should be reproducible rather than e.g. order-dependent.
+        assert_eq!(DisplayMonitor::get_fingerprint(), DisplayMonitor::get_fingerprint());
     }
 
     #[test]
@@ -1,10 +1,15 @@
 //! Shutter sound suppressor for screen capture
-//! 
+//!
 //! Mutes system audio during capture to suppress shutter sounds on:
 //! - macOS: CoreGraphics always plays a sound
 //! - Linux/Wayland: Portal may play a sound
-//! 
+//!
 //! Windows and X11 are silent by default, so no action needed.
+//!
+//! [`AudioGuard::mute`] remembers whatever mute state the output device was
+//! already in before we touch it, and restores exactly that state on drop -
+//! instead of unconditionally unmuting, which would un-silence a user who'd
+//! already muted their speakers themselves before starting a capture.
 
 use std::env;
 use std::process::Command;
@@ -14,89 +19,131 @@ use std::sync::OnceLock;
 static HAS_WPCTL: OnceLock<bool> = OnceLock::new();
 static HAS_PACTL: OnceLock<bool> = OnceLock::new();
 
-pub struct AudioGuard;
+/// A held audio mute - restores the pre-capture mute state on drop.
+///
+/// # Example
+/// ```ignore
+/// let _audio = AudioGuard::mute();
+/// // ... do capture ...
+/// // Original mute state restored when `_audio` goes out of scope
+/// ```
+pub struct AudioGuard {
+    /// Mute state observed right before we muted, or `None` if it couldn't
+    /// be determined (we still mute, but restore to "unmuted" as a best
+    /// effort, matching the old unconditional-unmute behavior).
+    prev_muted: Option<bool>,
+    restored: bool,
+}
 
 impl AudioGuard {
-    /// Mute system audio (call before capture)
+    /// Mute system audio (call before capture), remembering its prior state.
     #[inline]
-    pub fn mute() {
+    pub fn mute() -> Self {
+        let prev_muted = Self::is_muted();
+
         #[cfg(target_os = "macos")]
-        Self::mute_macos();
+        Self::set_muted_macos(true);
 
         #[cfg(target_os = "linux")]
         if Self::is_wayland() {
-            Self::mute_linux();
+            Self::set_muted_linux(true);
         }
         // Windows & X11: no-op
+
+        Self {
+            prev_muted,
+            restored: false,
+        }
     }
 
-    /// Unmute system audio (call after capture)
+    /// Restore the mute state audio was in before [`AudioGuard::mute`] was
+    /// called. Safe to call more than once; only the first call has effect.
     #[inline]
-    pub fn unmute() {
+    pub fn restore(&mut self) {
+        if self.restored {
+            return;
+        }
+        self.restored = true;
+        let restore_to = self.prev_muted.unwrap_or(false);
+
         #[cfg(target_os = "macos")]
-        Self::unmute_macos();
+        Self::set_muted_macos(restore_to);
 
         #[cfg(target_os = "linux")]
         if Self::is_wayland() {
-            Self::unmute_linux();
+            Self::set_muted_linux(restore_to);
         }
         // Windows & X11: no-op
     }
 
+    /// Query whether the default output device is currently muted. Returns
+    /// `None` on platforms/configs where we have no way to ask (e.g. X11, or
+    /// Linux with neither wpctl nor pactl present - the amixer fallback is
+    /// only used to set the mute state, never to query it).
+    fn is_muted() -> Option<bool> {
+        #[cfg(target_os = "macos")]
+        return Self::is_muted_macos();
+
+        #[cfg(target_os = "linux")]
+        if Self::is_wayland() {
+            return Self::is_muted_linux();
+        }
+
+        #[cfg(not(target_os = "macos"))]
+        None
+    }
+
     // ========== macOS ==========
-    
+
     #[cfg(target_os = "macos")]
-    fn mute_macos() {
-        // Using osascript for now - fast enough with flush
-        // TODO: Replace with native CoreAudio FFI for zero-delay:
-        // AudioObjectSetPropertyData(kAudioDevicePropertyMute)
-        let _ = Command::new("osascript")
-            .args(["-e", "set volume with output muted"])
-            .output();
+    fn is_muted_macos() -> Option<bool> {
+        coreaudio::default_output_is_muted()
     }
 
     #[cfg(target_os = "macos")]
-    fn unmute_macos() {
-        let _ = Command::new("osascript")
-            .args(["-e", "set volume without output muted"])
-            .output();
+    fn set_muted_macos(muted: bool) {
+        coreaudio::set_default_output_muted(muted);
     }
 
     // ========== Linux (Wayland only) ==========
 
     #[cfg(target_os = "linux")]
-    fn mute_linux() {
+    fn is_muted_linux() -> Option<bool> {
         if *HAS_WPCTL.get_or_init(|| Self::has_cmd("wpctl")) {
-            // PipeWire/WirePlumber (fastest, standard on modern Wayland)
-            let _ = Command::new("wpctl")
-                .args(["set-mute", "@DEFAULT_AUDIO_SINK@", "1"])
-                .output();
+            let output = Command::new("wpctl")
+                .args(["get-volume", "@DEFAULT_AUDIO_SINK@"])
+                .output()
+                .ok()?;
+            Some(String::from_utf8_lossy(&output.stdout).contains("MUTED"))
         } else if *HAS_PACTL.get_or_init(|| Self::has_cmd("pactl")) {
-            // PulseAudio fallback
-            let _ = Command::new("pactl")
-                .args(["set-sink-mute", "@DEFAULT_SINK@", "1"])
-                .output();
+            let output = Command::new("pactl")
+                .args(["get-sink-mute", "@DEFAULT_SINK@"])
+                .output()
+                .ok()?;
+            Some(String::from_utf8_lossy(&output.stdout).contains("yes"))
         } else {
-            // ALSA fallback (rare)
-            let _ = Command::new("amixer")
-                .args(["-q", "sset", "Master", "mute"])
-                .output();
+            None
         }
     }
 
     #[cfg(target_os = "linux")]
-    fn unmute_linux() {
+    fn set_muted_linux(muted: bool) {
+        let flag = if muted { "1" } else { "0" };
         if *HAS_WPCTL.get_or_init(|| Self::has_cmd("wpctl")) {
+            // PipeWire/WirePlumber (fastest, standard on modern Wayland)
             let _ = Command::new("wpctl")
-                .args(["set-mute", "@DEFAULT_AUDIO_SINK@", "0"])
+                .args(["set-mute", "@DEFAULT_AUDIO_SINK@", flag])
                 .output();
         } else if *HAS_PACTL.get_or_init(|| Self::has_cmd("pactl")) {
+            // PulseAudio fallback
             let _ = Command::new("pactl")
-                .args(["set-sink-mute", "@DEFAULT_SINK@", "0"])
+                .args(["set-sink-mute", "@DEFAULT_SINK@", flag])
                 .output();
         } else {
+            // ALSA fallback (rare)
+            let word = if muted { "mute" } else { "unmute" };
             let _ = Command::new("amixer")
-                .args(["-q", "sset", "Master", "unmute"])
+                .args(["-q", "sset", "Master", word])
                 .output();
         }
     }
@@ -120,6 +167,145 @@ impl AudioGuard {
     }
 }
 
+impl Drop for AudioGuard {
+    fn drop(&mut self) {
+        self.restore();
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod coreaudio {
+    //! Minimal CoreAudio FFI for the default output device's mute property.
+    //! Replaces the old `osascript -e 'set volume ...'` shell-out, which
+    //! paid a fresh process spawn (and a visible volume HUD flash) on every
+    //! mute/unmute.
+
+    use std::os::raw::c_void;
+
+    type OsStatus = i32;
+    type AudioObjectId = u32;
+    type AudioObjectPropertySelector = u32;
+    type AudioObjectPropertyScope = u32;
+    type AudioObjectPropertyElement = u32;
+
+    #[repr(C)]
+    struct AudioObjectPropertyAddress {
+        selector: AudioObjectPropertySelector,
+        scope: AudioObjectPropertyScope,
+        element: AudioObjectPropertyElement,
+    }
+
+    const fn fourcc(bytes: &[u8; 4]) -> u32 {
+        ((bytes[0] as u32) << 24)
+            | ((bytes[1] as u32) << 16)
+            | ((bytes[2] as u32) << 8)
+            | (bytes[3] as u32)
+    }
+
+    const K_AUDIO_OBJECT_SYSTEM_OBJECT: AudioObjectId = 1;
+    const K_AUDIO_OBJECT_PROPERTY_SCOPE_GLOBAL: u32 = fourcc(b"glob");
+    const K_AUDIO_OBJECT_PROPERTY_SCOPE_OUTPUT: u32 = fourcc(b"outp");
+    const K_AUDIO_OBJECT_PROPERTY_ELEMENT_MAIN: u32 = 0;
+    const K_AUDIO_HARDWARE_PROPERTY_DEFAULT_OUTPUT_DEVICE: u32 = fourcc(b"dOut");
+    const K_AUDIO_DEVICE_PROPERTY_MUTE: u32 = fourcc(b"mute");
+
+    #[link(name = "CoreAudio", kind = "framework")]
+    extern "C" {
+        fn AudioObjectGetPropertyData(
+            object_id: AudioObjectId,
+            address: *const AudioObjectPropertyAddress,
+            qualifier_data_size: u32,
+            qualifier_data: *const c_void,
+            io_data_size: *mut u32,
+            out_data: *mut c_void,
+        ) -> OsStatus;
+
+        fn AudioObjectSetPropertyData(
+            object_id: AudioObjectId,
+            address: *const AudioObjectPropertyAddress,
+            qualifier_data_size: u32,
+            qualifier_data: *const c_void,
+            in_data_size: u32,
+            in_data: *const c_void,
+        ) -> OsStatus;
+    }
+
+    fn default_output_device() -> Option<AudioObjectId> {
+        let address = AudioObjectPropertyAddress {
+            selector: K_AUDIO_HARDWARE_PROPERTY_DEFAULT_OUTPUT_DEVICE,
+            scope: K_AUDIO_OBJECT_PROPERTY_SCOPE_GLOBAL,
+            element: K_AUDIO_OBJECT_PROPERTY_ELEMENT_MAIN,
+        };
+        let mut device: AudioObjectId = 0;
+        let mut size = std::mem::size_of::<AudioObjectId>() as u32;
+        // SAFETY: `device`/`size` are valid, correctly-sized out-params for
+        // a `UInt32` property; CoreAudio writes at most `size` bytes.
+        let status = unsafe {
+            AudioObjectGetPropertyData(
+                K_AUDIO_OBJECT_SYSTEM_OBJECT,
+                &address,
+                0,
+                std::ptr::null(),
+                &mut size,
+                &mut device as *mut _ as *mut c_void,
+            )
+        };
+        if status != 0 {
+            return None;
+        }
+        Some(device)
+    }
+
+    fn mute_address() -> AudioObjectPropertyAddress {
+        AudioObjectPropertyAddress {
+            selector: K_AUDIO_DEVICE_PROPERTY_MUTE,
+            scope: K_AUDIO_OBJECT_PROPERTY_SCOPE_OUTPUT,
+            element: K_AUDIO_OBJECT_PROPERTY_ELEMENT_MAIN,
+        }
+    }
+
+    pub fn default_output_is_muted() -> Option<bool> {
+        let device = default_output_device()?;
+        let address = mute_address();
+        let mut muted: u32 = 0;
+        let mut size = std::mem::size_of::<u32>() as u32;
+        // SAFETY: same contract as `default_output_device` above.
+        let status = unsafe {
+            AudioObjectGetPropertyData(
+                device,
+                &address,
+                0,
+                std::ptr::null(),
+                &mut size,
+                &mut muted as *mut _ as *mut c_void,
+            )
+        };
+        if status != 0 {
+            return None;
+        }
+        Some(muted != 0)
+    }
+
+    pub fn set_default_output_muted(muted: bool) {
+        let Some(device) = default_output_device() else {
+            return;
+        };
+        let address = mute_address();
+        let value: u32 = muted as u32;
+        // SAFETY: `value` is a valid, correctly-sized `UInt32` in-param.
+        unsafe {
+            AudioObjectSetPropertyData(
+                device,
+                &address,
+                0,
+                std::ptr::null(),
+                std::mem::size_of::<u32>() as u32,
+                &value as *const _ as *const c_void,
+            );
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -127,7 +313,7 @@ mod tests {
     #[test]
     fn test_mute_unmute_doesnt_panic() {
         // Just verify no panic - actual audio state is system-dependent
-        AudioGuard::mute();
-        AudioGuard::unmute();
+        let guard = AudioGuard::mute();
+        drop(guard);
     }
-}
\ No newline at end of file
+}
@@ -1,41 +1,113 @@
-//! Single instance lock for preventing multiple capture overlays
+//! Single instance lock + activation channel for preventing multiple
+//! capture overlays
 //!
 //! Creates a .lock file to ensure only one capture session runs at a time.
-//! This prevents double freezes and multiple overlays.
+//! This prevents double freezes and multiple overlays. Unlike a bare lock,
+//! the holder also listens on an activation channel - a Unix domain socket
+//! under the lock directory on Linux/macOS, a named pipe on Windows: if a
+//! second launch finds the lock already held, instead of just giving up it
+//! sends the running instance [`ActivationCommand::Show`] to raise its
+//! overlay (the listener also accepts `Cancel`, to tear the overlay down,
+//! though `try_acquire` never sends it) - so a user who double-launches the
+//! capture shortcut gets a useful result instead of a silent no-op second
+//! process. A socket file under `XDG_RUNTIME_DIR` (typically mode 0700) and
+//! a named pipe are both scoped to the current user, unlike a loopback TCP
+//! port, which any local process could connect to.
 //!
 //! IMPORTANT: The lock is automatically released on drop, but if the process
 //! crashes, the lock file may remain. The OS file lock (via fs2) handles this
 //! gracefully - a stale lock file without an active lock can be re-acquired.
+//! A stale socket file from a crashed instance is harmless too: we remove it
+//! before binding our own.
 
-use anyhow::{anyhow, Context, Result};
+use anyhow::{Context, Result};
 use fs2::FileExt;
 use std::fs::{self, File};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+
+/// A command sent over the activation channel to an already-running instance.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActivationCommand {
+    /// Bring the running instance's overlay to the front.
+    Show,
+    /// Ask the running instance to cancel its in-progress capture.
+    Cancel,
+}
+
+impl ActivationCommand {
+    fn as_wire(self) -> &'static str {
+        match self {
+            ActivationCommand::Show => "SHOW",
+            ActivationCommand::Cancel => "CANCEL",
+        }
+    }
+
+    fn parse(line: &str) -> Option<Self> {
+        match line.trim() {
+            "SHOW" => Some(ActivationCommand::Show),
+            "CANCEL" => Some(ActivationCommand::Cancel),
+            _ => None,
+        }
+    }
+}
+
+/// Outcome of [`InstanceLock::try_acquire`].
+pub enum Activation {
+    /// No other instance was running; this process now holds the lock and
+    /// owns the activation channel.
+    Acquired(InstanceLock),
+    /// Another instance was already running; a `Show` command was sent to
+    /// its activation channel on a best-effort basis (delivery is not
+    /// confirmed, e.g. if a crashed instance left only a stale lock).
+    Activated,
+}
 
 /// A held instance lock - automatically releases on drop
 pub struct InstanceLock {
     file: File,
     path: PathBuf,
+    running: Arc<AtomicBool>,
+    listener: Option<JoinHandle<()>>,
+    #[cfg(unix)]
+    socket_path: PathBuf,
+    /// Only needed to re-derive the pipe name for the shutdown nudge below.
+    #[cfg(windows)]
+    app_name: String,
 }
 
 impl InstanceLock {
-    /// Try to acquire the instance lock.
-    /// 
-    /// Returns Ok(lock) if this is the only running instance.
-    /// Returns Err if another instance is already running.
-    /// 
+    /// Try to acquire the instance lock and start its activation channel.
+    ///
+    /// Returns `Activation::Acquired(lock)` if this is the only running
+    /// instance. If another instance is already running, it is sent a
+    /// `Show` command instead, and this returns `Activation::Activated`.
+    ///
+    /// `on_command` fires, on a background thread, once per activation
+    /// command received for as long as the returned lock is held.
+    ///
     /// # Example
     /// ```ignore
-    /// let _lock = InstanceLock::try_acquire("my-capture-app")?;
+    /// let lock = match InstanceLock::try_acquire("my-capture-app", |cmd| {
+    ///     eprintln!("activation channel received {:?}", cmd);
+    /// })? {
+    ///     Activation::Acquired(lock) => lock,
+    ///     Activation::Activated => return Ok(()), // raised the other instance
+    /// };
     /// // ... do capture ...
-    /// // Lock automatically released when _lock goes out of scope
+    /// // Lock and activation channel automatically released when `lock` drops
     /// ```
-    pub fn try_acquire(app_name: &str) -> Result<Self> {
+    pub fn try_acquire<F>(app_name: &str, on_command: F) -> Result<Activation>
+    where
+        F: Fn(ActivationCommand) + Send + 'static,
+    {
         let dir = Self::lock_dir()?;
         fs::create_dir_all(&dir)?;
-        
+
         let path = dir.join(format!("{}.lock", app_name));
-        
+
         let file = fs::OpenOptions::new()
             .read(true)
             .write(true)
@@ -45,27 +117,72 @@ impl InstanceLock {
             .with_context(|| format!("Failed to open lock file: {:?}", path))?;
 
         // Try exclusive lock - fails immediately if locked by another process
-        file.try_lock_exclusive()
-            .map_err(|_| anyhow!("Another instance is already running (lock: {:?})", path))?;
+        if file.try_lock_exclusive().is_err() {
+            Self::activate_running_instance(app_name, &dir);
+            return Ok(Activation::Activated);
+        }
+
+        let running = Arc::new(AtomicBool::new(true));
+        let accept_running = running.clone();
+
+        #[cfg(unix)]
+        let (listener, socket_path) = {
+            let socket_path = dir.join(format!("{}.sock", app_name));
+            let sock = unix_channel::bind(&socket_path)
+                .context("Failed to bind activation socket")?;
+            let handle = std::thread::spawn(move || {
+                unix_channel::accept_loop(sock, accept_running, on_command)
+            });
+            (handle, socket_path)
+        };
 
-        Ok(Self { file, path })
+        #[cfg(windows)]
+        let listener = windows_channel::spawn_accept_loop(app_name, accept_running, on_command)
+            .context("Failed to start activation channel")?;
+
+        Ok(Activation::Acquired(Self {
+            file,
+            path,
+            running,
+            listener: Some(listener),
+            #[cfg(unix)]
+            socket_path,
+            #[cfg(windows)]
+            app_name: app_name.to_string(),
+        }))
     }
 
     /// Force release a potentially stale lock (emergency cleanup)
-    /// 
+    ///
     /// This removes the lock file entirely. Use with caution - only when
     /// you're certain no other instance is running.
     pub fn force_release(app_name: &str) -> Result<()> {
         let dir = Self::lock_dir()?;
         let path = dir.join(format!("{}.lock", app_name));
-        
+
         if path.exists() {
             fs::remove_file(&path)
                 .with_context(|| format!("Failed to remove stale lock: {:?}", path))?;
         }
+        #[cfg(unix)]
+        let _ = fs::remove_file(dir.join(format!("{}.sock", app_name)));
         Ok(())
     }
 
+    /// Best-effort: reach the running instance's activation channel and
+    /// send it `Show`. Silently gives up if nobody's listening anymore
+    /// (stale socket/pipe from a crash) - the caller treats that the same
+    /// as a successful activation, since there's nothing useful left to do.
+    fn activate_running_instance(app_name: &str, dir: &Path) {
+        #[cfg(unix)]
+        unix_channel::send(&dir.join(format!("{}.sock", app_name)), ActivationCommand::Show);
+        #[cfg(windows)]
+        {
+            let _ = dir; // unused on Windows: the pipe namespace isn't filesystem-scoped
+            windows_channel::send(app_name, ActivationCommand::Show);
+        }
+    }
+
     /// Get the lock directory (XDG_RUNTIME_DIR or fallback to cache)
     fn lock_dir() -> Result<PathBuf> {
         dirs::runtime_dir()
@@ -76,10 +193,234 @@ impl InstanceLock {
 
 impl Drop for InstanceLock {
     fn drop(&mut self) {
-        // Unlock the file
+        // Tell the accept loop to stop; it notices on its next poll rather
+        // than us joining here, since joining in `drop` could block.
+        self.running.store(false, Ordering::Relaxed);
+        #[cfg(windows)]
+        // A blocked `ConnectNamedPipe` only wakes up on a real connection;
+        // nudge it with a self-connect so shutdown doesn't wait on one.
+        windows_channel::send(&self.app_name, ActivationCommand::Show);
+        self.listener.take();
+
         let _ = self.file.unlock();
-        // Remove the lock file (harmless if it fails)
         let _ = fs::remove_file(&self.path);
+        #[cfg(unix)]
+        let _ = fs::remove_file(&self.socket_path);
+    }
+}
+
+// ========== Unix: domain socket ==========
+
+#[cfg(unix)]
+mod unix_channel {
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::path::Path;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
+    use super::ActivationCommand;
+
+    /// Bind a Unix domain socket at `path`, scoped to the current user by
+    /// the lock directory's own permissions (`XDG_RUNTIME_DIR` is 0700).
+    pub fn bind(path: &Path) -> std::io::Result<UnixListener> {
+        // Remove a stale socket file from a crashed previous run - bind
+        // fails with `AddrInUse` otherwise.
+        let _ = std::fs::remove_file(path);
+        let listener = UnixListener::bind(path)?;
+        listener.set_nonblocking(true)?;
+        Ok(listener)
+    }
+
+    pub fn accept_loop<F>(listener: UnixListener, running: Arc<AtomicBool>, on_command: F)
+    where
+        F: Fn(ActivationCommand) + Send + 'static,
+    {
+        while running.load(Ordering::Relaxed) {
+            match listener.accept() {
+                Ok((stream, _)) => {
+                    if let Some(cmd) = read_command(stream) {
+                        on_command(cmd);
+                    }
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                    thread::sleep(Duration::from_millis(100));
+                }
+                Err(_) => break,
+            }
+        }
+    }
+
+    fn read_command(stream: UnixStream) -> Option<ActivationCommand> {
+        let mut line = String::new();
+        BufReader::new(stream).read_line(&mut line).ok()?;
+        ActivationCommand::parse(&line)
+    }
+
+    pub fn send(path: &Path, command: ActivationCommand) {
+        if let Ok(mut stream) = UnixStream::connect(path) {
+            let _ = writeln!(stream, "{}", command.as_wire());
+        }
+    }
+}
+
+// ========== Windows: named pipe ==========
+
+#[cfg(windows)]
+mod windows_channel {
+    use std::ffi::c_void;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::thread::{self, JoinHandle};
+
+    use windows_sys::Win32::Foundation::{
+        CloseHandle, GetLastError, ERROR_PIPE_CONNECTED, GENERIC_READ, GENERIC_WRITE, HANDLE,
+        INVALID_HANDLE_VALUE,
+    };
+    use windows_sys::Win32::Storage::FileSystem::{
+        CreateFileW, ReadFile, WriteFile, FILE_ATTRIBUTE_NORMAL, OPEN_EXISTING,
+    };
+    use windows_sys::Win32::System::Pipes::{
+        ConnectNamedPipe, CreateNamedPipeW, DisconnectNamedPipe, PIPE_ACCESS_DUPLEX,
+        PIPE_READMODE_BYTE, PIPE_TYPE_BYTE, PIPE_UNLIMITED_INSTANCES, PIPE_WAIT,
+    };
+
+    use super::ActivationCommand;
+
+    fn pipe_name(app_name: &str) -> Vec<u16> {
+        format!("\\\\.\\pipe\\{app_name}\0").encode_utf16().collect()
+    }
+
+    /// Owns a server-side pipe instance handle, closing it on drop.
+    struct PipeHandle(HANDLE);
+    // SAFETY: a Windows HANDLE has no thread affinity.
+    unsafe impl Send for PipeHandle {}
+    impl Drop for PipeHandle {
+        fn drop(&mut self) {
+            unsafe {
+                CloseHandle(self.0);
+            }
+        }
+    }
+
+    fn create_instance(name: &[u16]) -> Option<PipeHandle> {
+        // SAFETY: `name` is a valid NUL-terminated UTF-16 string; the rest
+        // are plain integer parameters per `CreateNamedPipeW`'s contract.
+        let handle = unsafe {
+            CreateNamedPipeW(
+                name.as_ptr(),
+                PIPE_ACCESS_DUPLEX,
+                PIPE_TYPE_BYTE | PIPE_READMODE_BYTE | PIPE_WAIT,
+                PIPE_UNLIMITED_INSTANCES,
+                512,
+                512,
+                0,
+                std::ptr::null_mut(),
+            )
+        };
+        if handle == INVALID_HANDLE_VALUE {
+            None
+        } else {
+            Some(PipeHandle(handle))
+        }
+    }
+
+    /// Spawn the accept loop: repeatedly create a pipe instance, block in
+    /// `ConnectNamedPipe` for a client, dispatch its command, disconnect,
+    /// and repeat while `running` is true.
+    pub fn spawn_accept_loop<F>(
+        app_name: &str,
+        running: Arc<AtomicBool>,
+        on_command: F,
+    ) -> Option<JoinHandle<()>>
+    where
+        F: Fn(ActivationCommand) + Send + 'static,
+    {
+        let name = pipe_name(app_name);
+        Some(thread::spawn(move || {
+            while running.load(Ordering::Relaxed) {
+                let Some(pipe) = create_instance(&name) else {
+                    break;
+                };
+                // SAFETY: `pipe.0` is a valid, freshly-created pipe server
+                // handle; this blocks until a client connects (or a client
+                // raced in first, signalled by `ERROR_PIPE_CONNECTED`).
+                let connected = unsafe { ConnectNamedPipe(pipe.0, std::ptr::null_mut()) != 0 };
+                if !connected && unsafe { GetLastError() } != ERROR_PIPE_CONNECTED {
+                    continue;
+                }
+                // Drop's self-connect nudge lands here too - bail out
+                // before dispatching a bogus command on shutdown.
+                if !running.load(Ordering::Relaxed) {
+                    break;
+                }
+                if let Some(cmd) = read_command(pipe.0) {
+                    on_command(cmd);
+                }
+                unsafe {
+                    DisconnectNamedPipe(pipe.0);
+                }
+            }
+        }))
+    }
+
+    fn read_command(handle: HANDLE) -> Option<ActivationCommand> {
+        let mut buf = [0u8; 64];
+        let mut read = 0u32;
+        // SAFETY: `buf`/`read` are a valid out-buffer and length pointer
+        // sized to match.
+        let ok = unsafe {
+            ReadFile(
+                handle,
+                buf.as_mut_ptr() as *mut c_void,
+                buf.len() as u32,
+                &mut read,
+                std::ptr::null_mut(),
+            )
+        };
+        if ok == 0 || read == 0 {
+            return None;
+        }
+        ActivationCommand::parse(&String::from_utf8_lossy(&buf[..read as usize]))
+    }
+
+    /// Best-effort: connect to the running instance's named pipe and send
+    /// it `command`.
+    pub fn send(app_name: &str, command: ActivationCommand) {
+        let name = pipe_name(app_name);
+        // SAFETY: opening a named pipe as a client via `CreateFileW` with
+        // `OPEN_EXISTING` is the standard pattern; a null security
+        // descriptor inherits the default ACL.
+        let handle = unsafe {
+            CreateFileW(
+                name.as_ptr(),
+                GENERIC_READ | GENERIC_WRITE,
+                0,
+                std::ptr::null(),
+                OPEN_EXISTING,
+                FILE_ATTRIBUTE_NORMAL,
+                0,
+            )
+        };
+        if handle == INVALID_HANDLE_VALUE {
+            return;
+        }
+        let payload = format!("{}\n", command.as_wire());
+        let mut written = 0u32;
+        // SAFETY: `handle` was just opened above and `payload` outlives
+        // the call.
+        unsafe {
+            WriteFile(
+                handle,
+                payload.as_ptr() as *const c_void,
+                payload.len() as u32,
+                &mut written,
+                std::ptr::null_mut(),
+            );
+            CloseHandle(handle);
+        }
     }
 }
 
@@ -90,30 +431,39 @@ mod tests {
     #[test]
     fn test_acquire_and_release() {
         let app_name = "test-single-instance-123";
-        
+
         // First acquire should succeed
-        let lock = InstanceLock::try_acquire(app_name);
-        assert!(lock.is_ok(), "First lock should succeed");
-        
-        // Second acquire should fail
-        let lock2 = InstanceLock::try_acquire(app_name);
-        assert!(lock2.is_err(), "Second lock should fail");
-        
+        let first = InstanceLock::try_acquire(app_name, |_| {});
+        assert!(first.is_ok(), "First lock should succeed");
+        let lock = match first.unwrap() {
+            Activation::Acquired(lock) => lock,
+            Activation::Activated => panic!("First launch should acquire, not activate"),
+        };
+
+        // Second acquire should activate the first instance instead of
+        // acquiring its own lock.
+        let second = InstanceLock::try_acquire(app_name, |_| {});
+        assert!(matches!(second.unwrap(), Activation::Activated));
+
         // Release first lock
         drop(lock);
-        
+
         // Now acquire should succeed again
-        let lock3 = InstanceLock::try_acquire(app_name);
-        assert!(lock3.is_ok(), "Lock after release should succeed");
+        let third = InstanceLock::try_acquire(app_name, |_| {});
+        assert!(matches!(third.unwrap(), Activation::Acquired(_)));
     }
 
     #[test]
     fn test_force_release() {
         let app_name = "test-force-release-456";
-        let _lock = InstanceLock::try_acquire(app_name).unwrap();
-        
+        let lock = InstanceLock::try_acquire(app_name, |_| {}).unwrap();
+        let _lock = match lock {
+            Activation::Acquired(lock) => lock,
+            Activation::Activated => panic!("Expected to acquire"),
+        };
+
         // Force release while locked - this removes the file but lock remains
-        // until _lock is dropped (OS behavior)
+        // until `_lock` is dropped (OS behavior)
         let result = InstanceLock::force_release(app_name);
         assert!(result.is_ok());
     }
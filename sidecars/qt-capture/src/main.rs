@@ -6,112 +6,276 @@
 //! - Display hotplug monitoring (kill on cable unplug)
 //! - Shutter sound suppression (mute during capture)
 //!
-//! IPC Protocol (Qt → Rust via stdout):
-//! - REQ_MUTE: Mute audio before capture
-//! - CAPTURE_SUCCESS: Capture completed successfully
-//! - CAPTURE_FAIL: Capture failed or cancelled
+//! IPC Protocol (Qt → Rust via stdout): see [`protocol`]. Qt must emit the
+//! [`protocol::HANDSHAKE`] line before anything else, followed by a stream
+//! of `TAG\tPAYLOAD` messages. The wrapper re-emits the final `Result` (or
+//! `Error`) on its own stdout in the same framed form so a Tauri caller can
+//! consume structured output instead of scraping a bare path line.
+//!
+//! The reverse direction (wrapper → Qt) is a single best-effort `SHOW` line
+//! written to Qt's stdin when a second launch's activation channel asks this
+//! instance to come to front - it isn't part of [`protocol`] since it's not
+//! framed the same way and Qt isn't required to act on it, the same way
+//! [`protocol::Message::Mute`] is accepted but now informational.
+
+mod protocol;
 
 use std::env;
 use std::io::{BufRead, BufReader};
 use std::path::PathBuf;
-use std::process::{Child, Command, ExitCode, Stdio};
+use std::process::{Command, ExitCode, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
 
 use anyhow::{Context, Result};
+use sys_capture_supervisor::{StopSignal, Supervisor, TerminateConfig};
 use sys_display_hotplug::DisplayWatcher;
 use sys_shutter_suppressor::AudioGuard;
-use sys_single_instance::InstanceLock;
+use sys_single_instance::{Activation, ActivationCommand, InstanceLock};
+
+use protocol::Message;
+
+/// Capture completed and a path was produced.
+const EXIT_OK: u8 = 0;
+/// Capture failed, was cancelled, or produced no usable path.
+const EXIT_CAPTURE_FAIL: u8 = 1;
+/// The capture was torn down because the display topology changed mid-capture.
+const EXIT_DISPLAY_UNPLUGGED: u8 = 2;
+/// The capture was torn down because Qt never finished within the timeout.
+const EXIT_TIMEOUT: u8 = 3;
+/// Another instance was already running; it was sent `SHOW` on its
+/// activation channel instead of starting a redundant capture.
+const EXIT_ALREADY_RUNNING: u8 = 4;
+
+/// How long Qt may run (e.g. before sending its `RESULT`/`ERROR` message)
+/// before the watchdog gives up on it as stalled. Overridable via `QTCAP_TIMEOUT_SECS`; `0` disables
+/// the watchdog entirely.
+const DEFAULT_TIMEOUT_SECS: u64 = 30;
 
 fn main() -> ExitCode {
     match run() {
         Ok(code) => code,
         Err(e) => {
             eprintln!("[qt-capture] Error: {:#}", e);
-            AudioGuard::unmute(); // Safety unmute on error
             ExitCode::from(1)
         }
     }
 }
 
 fn run() -> Result<ExitCode> {
-    // 1. Acquire single instance lock
-    let _lock = InstanceLock::try_acquire("qt-capture")
-        .context("Failed to acquire instance lock - is another capture running?")?;
+    // 1. Acquire single instance lock. If another capture is already
+    // running, it gets sent `Show` on its activation channel and we bow out
+    // instead of starting a second, redundant overlay. The callback fires
+    // before Qt is spawned below (a second launch can race this one), so it
+    // closes over a shared slot that step 3 fills in once `child` exists,
+    // rather than requiring `child` up front.
+    let activation_child: Arc<Mutex<Option<Arc<Supervisor>>>> = Arc::new(Mutex::new(None));
+    let on_command_child = activation_child.clone();
+    let activation = InstanceLock::try_acquire("qt-capture", move |cmd| {
+        let Some(child) = on_command_child.lock().unwrap().clone() else {
+            eprintln!("[qt-capture] Activation channel received {:?} before Qt was ready; ignoring", cmd);
+            return;
+        };
+        match cmd {
+            ActivationCommand::Show => {
+                // Best-effort: Qt isn't required to read or act on this.
+                if let Err(e) = child.send_line("SHOW") {
+                    eprintln!("[qt-capture] Failed to forward Show to Qt: {:#}", e);
+                }
+            }
+            ActivationCommand::Cancel => {
+                eprintln!("[qt-capture] Activation channel asked to cancel; terminating Qt...");
+                child.terminate(&terminate_config());
+            }
+        }
+    })
+    .context("Failed to start instance lock / activation channel")?;
+    let _lock = match activation {
+        Activation::Acquired(lock) => lock,
+        Activation::Activated => {
+            eprintln!("[qt-capture] Another capture is already running; asked it to come to front.");
+            return Ok(ExitCode::from(EXIT_ALREADY_RUNNING));
+        }
+    };
 
-    // 2. Mute audio BEFORE spawning Qt (Portal shutter plays during captureAll)
-    AudioGuard::mute();
+    // 2. Mute audio BEFORE spawning Qt (Portal shutter plays during captureAll).
+    // Held for the rest of `run()`; its prior mute state is restored on drop,
+    // covering every early-return path below as well as the happy path.
+    let _audio = AudioGuard::mute();
 
-    // 3. Spawn Qt child process
+    // 3. Spawn Qt child process under a Supervisor, which owns a pidfd for
+    // it on Linux >= 5.3. That lets the hotplug callback and the watchdog
+    // below terminate Qt through the pidfd rather than its raw PID, so
+    // neither can race a reused PID there (without a pidfd, e.g. on macOS,
+    // the Supervisor signals the raw PID), and `on_exit`/`wait` learn of
+    // exit instantly instead of polling.
     let args: Vec<String> = env::args().skip(1).collect();
-    let mut child = spawn_qt_child(&args)?;
-    let child_pid = child.id();
+    let child = Arc::new(spawn_qt_child(&args)?);
+    *activation_child.lock().unwrap() = Some(child.clone());
+    let manually_killed = Arc::new(AtomicBool::new(false));
+    let timed_out = Arc::new(AtomicBool::new(false));
 
     // 4. Start display hotplug monitor (background thread)
+    let watcher_child = child.clone();
+    let watcher_killed = manually_killed.clone();
     let watcher = DisplayWatcher::start(move || {
-        eprintln!("[qt-capture] Display topology changed! Killing Qt...");
-        kill_process(child_pid);
+        eprintln!("[qt-capture] Display topology changed! Terminating Qt...");
+        watcher_killed.store(true, Ordering::SeqCst);
+        watcher_child.terminate(&terminate_config());
         // Note: This callback runs in background thread, so we can't return from main here.
         // The IPC loop will detect the child died and exit.
     });
 
-    // 5. IPC loop - read Qt stdout
-    let exit_code = if let Some(stdout) = child.stdout.take() {
-        let reader = BufReader::new(stdout);
-        let mut capture_success = false;
-        let mut capture_path: Option<String> = None;
-
-        for line in reader.lines() {
-            match line {
-                Ok(msg) => {
-                    let trimmed = msg.trim();
-                    match trimmed {
-                        "REQ_MUTE" => {
-                            // Already muted at startup - this is a no-op now
-                            // Kept for backwards compatibility
-                        }
-                        "CAPTURE_SUCCESS" => {
-                            capture_success = true;
-                        }
-                        "CAPTURE_FAIL" => {
-                            capture_success = false;
-                            break;
-                        }
-                        _ => {
-                            // Check if it's a path (starts with /)
-                            if trimmed.starts_with('/') && capture_success {
-                                capture_path = Some(trimmed.to_string());
-                                break;
-                            } else {
-                                // Passthrough Qt debug output
-                                eprintln!("[Qt] {}", trimmed);
-                            }
-                        }
-                    }
+    // 4b. Start the capture watchdog - if Qt stalls before emitting a
+    // result (e.g. the Portal dialog never returns), force it down instead
+    // of blocking `reader.lines()` forever with audio left muted.
+    let watchdog_child = child.clone();
+    let watchdog_timed_out = timed_out.clone();
+    let _watchdog = thread::spawn(move || {
+        let timeout = capture_timeout();
+        if timeout.is_zero() {
+            return;
+        }
+        let deadline = Instant::now() + timeout;
+        while Instant::now() < deadline {
+            if watchdog_child.try_wait().is_some() {
+                return; // Qt already exited on its own
+            }
+            thread::sleep(Duration::from_millis(200));
+        }
+        if watchdog_child.try_wait().is_some() {
+            return;
+        }
+        eprintln!("[qt-capture] Capture timed out after {:?}, terminating Qt...", timeout);
+        watchdog_timed_out.store(true, Ordering::SeqCst);
+        watchdog_child.terminate(&terminate_config());
+    });
+
+    // 5. IPC loop - read framed Qt stdout
+    let exit_code = if let Some(stdout) = child.take_stdout() {
+        let mut lines = BufReader::new(stdout).lines();
+
+        match lines.next() {
+            Some(Ok(ref handshake)) if handshake.trim() == protocol::HANDSHAKE => {}
+            Some(Ok(other)) => {
+                anyhow::bail!(
+                    "Qt binary speaks an incompatible protocol (expected `{}`, got `{}`)",
+                    protocol::HANDSHAKE,
+                    other.trim()
+                );
+            }
+            _ => anyhow::bail!("Qt binary exited before sending the protocol handshake"),
+        }
+
+        let mut result: Option<Message> = None;
+
+        for line in lines {
+            let Ok(msg) = line else { break };
+            let trimmed = msg.trim();
+            if trimmed.is_empty() {
+                continue;
+            }
+
+            match Message::parse(trimmed) {
+                Ok(Some(Message::Ready)) => {}
+                Ok(Some(Message::Mute)) => {
+                    // Already muted at startup - this is a no-op now.
+                    // Kept for backwards compatibility.
+                }
+                Ok(Some(Message::Progress(pct))) => {
+                    eprintln!("[qt-capture] progress: {}%", pct);
+                }
+                Ok(Some(msg @ Message::Result { .. })) => {
+                    result = Some(msg);
+                    break;
+                }
+                Ok(Some(msg @ Message::Error { .. })) => {
+                    result = Some(msg);
+                    break;
+                }
+                Ok(None) => {
+                    // Unknown tag from a newer Qt binary, or plain debug text.
+                    eprintln!("[Qt] {}", trimmed);
+                }
+                Err(e) => {
+                    eprintln!("[qt-capture] Malformed message from Qt ({:#}): {}", e, trimmed);
                 }
-                Err(_) => break,
             }
         }
 
-        // Output the capture path if successful
-        if let Some(path) = capture_path {
-            println!("{}", path);
-            ExitCode::from(0)
+        if timed_out.load(Ordering::SeqCst) {
+            ExitCode::from(EXIT_TIMEOUT)
+        } else if manually_killed.load(Ordering::SeqCst) {
+            ExitCode::from(EXIT_DISPLAY_UNPLUGGED)
         } else {
-            ExitCode::from(1)
+            match result {
+                Some(msg @ Message::Result { .. }) => {
+                    println!("{}", msg.encode());
+                    ExitCode::from(EXIT_OK)
+                }
+                Some(msg @ Message::Error { .. }) => {
+                    println!("{}", msg.encode());
+                    ExitCode::from(EXIT_CAPTURE_FAIL)
+                }
+                _ => ExitCode::from(EXIT_CAPTURE_FAIL),
+            }
         }
     } else {
-        ExitCode::from(1)
+        ExitCode::from(EXIT_CAPTURE_FAIL)
     };
 
-    // 5. Cleanup
+    // 6. Cleanup
     watcher.stop();
-    let _ = child.wait();
-    AudioGuard::unmute(); // Always unmute on exit
+    child.wait();
+    // `_audio` restores the pre-capture mute state when it drops below.
 
     Ok(exit_code)
 }
 
-/// Spawn the Qt binary as a child process
-fn spawn_qt_child(args: &[String]) -> Result<Child> {
+/// Resolve the capture watchdog timeout from `QTCAP_TIMEOUT_SECS`, falling
+/// back to [`DEFAULT_TIMEOUT_SECS`]. A value of `0` disables the watchdog.
+fn capture_timeout() -> Duration {
+    let secs = env::var("QTCAP_TIMEOUT_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_TIMEOUT_SECS);
+    Duration::from_secs(secs)
+}
+
+/// Resolve the graceful-stop escalation used to terminate Qt, from
+/// `QTCAP_STOP_SIGNAL` (`term`/`int`/`hup`) and `QTCAP_STOP_TIMEOUT_SECS`,
+/// falling back to [`TerminateConfig::default`] for either that's unset or
+/// unparseable.
+fn terminate_config() -> TerminateConfig {
+    let default = TerminateConfig::default();
+
+    let stop_signal = env::var("QTCAP_STOP_SIGNAL")
+        .ok()
+        .and_then(|v| match v.to_ascii_lowercase().as_str() {
+            "term" => Some(StopSignal::Term),
+            "int" => Some(StopSignal::Interrupt),
+            "hup" => Some(StopSignal::Hangup),
+            _ => None,
+        })
+        .unwrap_or(default.stop_signal);
+
+    let stop_timeout = env::var("QTCAP_STOP_TIMEOUT_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .map(Duration::from_secs)
+        .unwrap_or(default.stop_timeout);
+
+    TerminateConfig {
+        stop_signal,
+        stop_timeout,
+    }
+}
+
+/// Spawn the Qt binary as a supervised child process so the hotplug watcher
+/// and watchdog can terminate it without going through its raw PID.
+fn spawn_qt_child(args: &[String]) -> Result<Supervisor> {
     let exe_path = env::current_exe()?;
     let exe_dir = exe_path.parent().context("No parent dir for executable")?;
 
@@ -120,6 +284,7 @@ fn spawn_qt_child(args: &[String]) -> Result<Child> {
 
     let mut cmd = Command::new(&qt_bin);
     cmd.args(args)
+        .stdin(Stdio::piped()) // For forwarding activation commands, e.g. `SHOW`
         .stdout(Stdio::piped()) // Capture for IPC
         .stderr(Stdio::inherit()); // Let errors flow
 
@@ -140,7 +305,7 @@ fn spawn_qt_child(args: &[String]) -> Result<Child> {
             .env("QT_QPA_PLATFORM_PLUGIN_PATH", plugins_path.join("platforms"));
     }
 
-    cmd.spawn().context("Failed to spawn Qt binary")
+    Supervisor::spawn(cmd).context("Failed to spawn Qt binary")
 }
 
 /// Find Qt binary and runtime directory
@@ -181,20 +346,4 @@ fn find_qt_paths(exe_dir: &std::path::Path) -> Result<(PathBuf, PathBuf)> {
         "Qt binary not found. Expected qt-runtime directory at {:?}",
         qt_runtime
     )
-}
-
-/// Kill a process by PID
-fn kill_process(pid: u32) {
-    #[cfg(unix)]
-    {
-        use std::process::Command;
-        let _ = Command::new("kill").arg("-9").arg(pid.to_string()).output();
-    }
-    #[cfg(windows)]
-    {
-        use std::process::Command;
-        let _ = Command::new("taskkill")
-            .args(["/F", "/PID", &pid.to_string()])
-            .output();
-    }
 }
\ No newline at end of file
@@ -0,0 +1,158 @@
+//! Framed IPC protocol between the Qt capture binary and this wrapper.
+//!
+//! Each message from Qt is a single stdout line of the form `TAG\tPAYLOAD`.
+//! The very first line a compatible Qt binary emits must be the handshake
+//! [`HANDSHAKE`], so the wrapper can reject a Qt binary built against an
+//! incompatible protocol version before trusting anything else on the
+//! stream. Structured payloads (`Result`, `Error`) are a JSON object; other
+//! tags carry a plain-text payload. Unknown tags are logged by the caller
+//! and otherwise ignored - they must never be fatal, since a newer Qt
+//! binary may add metadata an older wrapper doesn't know about yet.
+
+use serde::{Deserialize, Serialize};
+
+/// Handshake line a compatible Qt binary must emit before any other message.
+pub const HANDSHAKE: &str = "QTCAP/1";
+
+/// A single parsed message from the Qt side of the protocol.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Message {
+    /// Qt finished initializing and is ready to capture.
+    Ready,
+    /// Qt is requesting the wrapper mute audio (legacy - the wrapper mutes
+    /// unconditionally before spawning Qt, so this is now informational).
+    Mute,
+    /// Capture progress, 0-100.
+    Progress(u8),
+    /// Capture finished successfully.
+    Result { paths: Vec<String>, kind: String },
+    /// Capture failed or was cancelled.
+    Error { code: String, message: String },
+}
+
+#[derive(Serialize, Deserialize)]
+struct ResultPayload {
+    paths: Vec<String>,
+    kind: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ErrorPayload {
+    code: String,
+    message: String,
+}
+
+impl Message {
+    /// Parse one protocol line (not the handshake line).
+    ///
+    /// Returns `Ok(None)` for an empty line, and `Err` only
+    /// when a recognized tag carries a payload that fails to parse. A tag
+    /// this wrapper doesn't know about is *not* an error - it parses as
+    /// `Ok(None)` so the caller can log it and keep reading.
+    pub fn parse(line: &str) -> anyhow::Result<Option<Self>> {
+        let (tag, payload) = line.split_once('\t').unwrap_or((line, ""));
+
+        Ok(match tag {
+            "READY" => Some(Message::Ready),
+            "MUTE" => Some(Message::Mute),
+            "PROGRESS" => Some(Message::Progress(payload.trim().parse()?)),
+            "RESULT" => {
+                let parsed: ResultPayload = serde_json::from_str(payload)?;
+                Some(Message::Result {
+                    paths: parsed.paths,
+                    kind: parsed.kind,
+                })
+            }
+            "ERROR" => {
+                let parsed: ErrorPayload = serde_json::from_str(payload)?;
+                Some(Message::Error {
+                    code: parsed.code,
+                    message: parsed.message,
+                })
+            }
+            _ => None,
+        })
+    }
+
+    /// Encode this message back into a single framed protocol line, for the
+    /// wrapper to re-emit structured output on its own stdout.
+    pub fn encode(&self) -> String {
+        match self {
+            Message::Ready => "READY".to_string(),
+            Message::Mute => "MUTE".to_string(),
+            Message::Progress(pct) => format!("PROGRESS\t{}", pct),
+            Message::Result { paths, kind } => {
+                let payload = serde_json::to_string(&ResultPayload {
+                    paths: paths.clone(),
+                    kind: kind.clone(),
+                })
+                .expect("ResultPayload always serializes");
+                format!("RESULT\t{}", payload)
+            }
+            Message::Error { code, message } => {
+                let payload = serde_json::to_string(&ErrorPayload {
+                    code: code.clone(),
+                    message: message.clone(),
+                })
+                .expect("ErrorPayload always serializes");
+                format!("ERROR\t{}", payload)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ready_and_mute() {
+        assert_eq!(Message::parse("READY").unwrap(), Some(Message::Ready));
+        assert_eq!(Message::parse("MUTE").unwrap(), Some(Message::Mute));
+    }
+
+    #[test]
+    fn test_parse_progress() {
+        assert_eq!(
+            Message::parse("PROGRESS\t42").unwrap(),
+            Some(Message::Progress(42))
+        );
+    }
+
+    #[test]
+    fn test_parse_progress_malformed_is_err() {
+        assert!(Message::parse("PROGRESS\tnot-a-number").is_err());
+    }
+
+    #[test]
+    fn test_parse_result_roundtrip() {
+        let msg = Message::Result {
+            paths: vec!["/tmp/shot.png".to_string()],
+            kind: "screenshot".to_string(),
+        };
+        let encoded = msg.encode();
+        let parsed = Message::parse(&encoded).unwrap();
+        assert_eq!(parsed, Some(msg));
+    }
+
+    #[test]
+    fn test_parse_error_roundtrip() {
+        let msg = Message::Error {
+            code: "CANCELLED".to_string(),
+            message: "user cancelled capture".to_string(),
+        };
+        let encoded = msg.encode();
+        let parsed = Message::parse(&encoded).unwrap();
+        assert_eq!(parsed, Some(msg));
+    }
+
+    #[test]
+    fn test_parse_result_malformed_payload_is_err() {
+        assert!(Message::parse("RESULT\t{not json}").is_err());
+    }
+
+    #[test]
+    fn test_parse_unknown_tag_is_none() {
+        assert_eq!(Message::parse("SOME_FUTURE_TAG\tpayload").unwrap(), None);
+    }
+}
@@ -9,7 +9,7 @@
 use anyhow::Result;
 use std::fs;
 
-use crate::utils::{project_root, run_cmd};
+use crate::utils::{project_root, run_cmd, target_triple};
 
 pub fn sidecar_dir() -> std::path::PathBuf {
     project_root().join("sidecars").join("qt-capture")
@@ -19,10 +19,13 @@ fn qt_native_dir() -> std::path::PathBuf {
     sidecar_dir().join("native")
 }
 
-pub fn build() -> Result<()> {
+pub fn build(target: Option<&str>) -> Result<()> {
     println!("\nBuilding Capture Engine...");
 
     // 1. Build Qt (CMake)
+    // Note: the Qt/CMake build always targets the host - there's no cross
+    // toolchain file wired up here, so `target` only affects the Rust
+    // wrapper and the artifact naming below.
     build_qt_native()?;
 
     // 2. Deploy Qt (Bundle)
@@ -37,10 +40,10 @@ pub fn build() -> Result<()> {
     }
 
     // 4. Build Rust Wrapper
-    build_rust_wrapper()?;
+    build_rust_wrapper(target)?;
 
     // 5. Package into app/binaries
-    package_artifacts()?;
+    package_artifacts(target)?;
 
     println!("\nCapture Engine build complete!");
     Ok(())
@@ -85,24 +88,30 @@ fn deploy_qt_native() -> Result<()> {
     Ok(())
 }
 
-fn build_rust_wrapper() -> Result<()> {
+fn build_rust_wrapper(target: Option<&str>) -> Result<()> {
     println!("\nBuilding Rust wrapper...");
 
     let _sidecar = sidecar_dir();
 
-    run_cmd(
-        "cargo",
-        &["build", "--release", "-p", "capture-engine"],
-        &project_root(),
-    )?;
+    let mut args = vec!["build", "--release", "-p", "capture-engine"];
+    if let Some(triple) = target {
+        args.push("--target");
+        args.push(triple);
+    }
+    run_cmd("cargo", &args, &project_root())?;
 
     Ok(())
 }
 
-fn package_artifacts() -> Result<()> {
+fn package_artifacts(target: Option<&str>) -> Result<()> {
     println!("\nPackaging artifacts for Tauri...");
 
-    let target_dir = project_root().join("target").join("release");
+    // `cargo build --target <triple>` nests output under target/<triple>/release
+    // instead of target/release.
+    let target_dir = match target {
+        Some(triple) => project_root().join("target").join(triple).join("release"),
+        None => project_root().join("target").join("release"),
+    };
     let qt_runtime_src = qt_native_dir().join("qt-runtime");
     
     // Tauri app structure
@@ -127,42 +136,36 @@ fn package_artifacts() -> Result<()> {
     }
 
     // 2. Copy and rename Rust binary
-    let src_binary_name = format!("capture-engine{}", if cfg!(windows) { ".exe" } else { "" });
+    //
+    // `cfg!(windows)` reflects the *host* xtask was compiled for, not the
+    // requested `--target` - cross-building for Windows from Linux/macOS
+    // still makes cargo emit `capture-engine.exe` under `target/<triple>/`,
+    // so the suffix must follow `triple`, not the host.
+    let host = target_triple();
+    let triple = target.unwrap_or(&host);
+    let is_windows_target = triple.contains("windows");
+
+    let src_binary_name = format!("capture-engine{}", if is_windows_target { ".exe" } else { "" });
     let src_binary_path = target_dir.join(&src_binary_name);
 
     if !src_binary_path.exists() {
         anyhow::bail!("Rust binary not found: {}", src_binary_path.display());
     }
 
-    let target_triple = sys_info::os_type().unwrap_or_else(|_| "unknown".to_string()); 
-    // Note: This is a rough guess. Ideally we use the actual target triple from cargo.
-    // But since we are running xtask on the host, we can assume host target.
-    // For now let's construct it properly via rustc or use a hardcoded guess since xtask is local.
-    // A safer bet for now is to just use a fixed suffix or the strict one requested if we knew it.
-    // User requested: ocr-engine-x86_64-unknown-linux-gnu.
-    // We should probably shell out to `rustc -vV` to get host triple or just use a helper.
-    let host_triple = get_host_target_triple()?;
-    
-    let dst_binary_name = format!("capture-engine-{}{}", host_triple, if cfg!(windows) { ".exe" } else { "" });
+    let dst_binary_name = format!("capture-engine-{}{}", triple, if is_windows_target { ".exe" } else { "" });
     let dst_binary_path = app_binaries.join(&dst_binary_name);
 
     println!("  Copying binary to {}", dst_binary_path.display());
     fs::copy(&src_binary_path, &dst_binary_path)?;
 
-    Ok(())
-}
-
-fn get_host_target_triple() -> Result<String> {
-    let output = std::process::Command::new("rustc")
-        .arg("-vV")
-        .output()?;
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    for line in stdout.lines() {
-        if line.starts_with("host: ") {
-            return Ok(line.trim_start_matches("host: ").trim().to_string());
-        }
+    if triple != host {
+        println!(
+            "  Note: built for {} on host {} - skipping any host-only smoke test",
+            triple, host
+        );
     }
-    Ok("unknown-target".to_string())
+
+    Ok(())
 }
 
 pub fn clean() -> Result<()> {
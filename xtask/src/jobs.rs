@@ -0,0 +1,140 @@
+// Copyright 2026 a7mddra
+// SPDX-License-Identifier: Apache-2.0
+
+//! Bounded-parallel execution of independent build steps.
+//!
+//! Modeled on the job-token executor the `cc` crate uses to keep parallel
+//! C compilation from oversubscribing the machine: a counting semaphore
+//! hands out a fixed number of permits, and each step acquires one before
+//! doing CPU-heavy work and releases it when done.
+
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+
+use anyhow::Result;
+
+/// A named, independent build step. Boxed so steps can close over
+/// per-invocation state (e.g. a `--target` triple) instead of being limited
+/// to bare function pointers.
+pub type Step = (&'static str, Box<dyn FnOnce() -> Result<()> + Send>);
+
+struct TokenPool {
+    available: Mutex<usize>,
+    cond: Condvar,
+}
+
+impl TokenPool {
+    fn new(permits: usize) -> Self {
+        Self {
+            available: Mutex::new(permits.max(1)),
+            cond: Condvar::new(),
+        }
+    }
+
+    fn acquire(&self) {
+        let mut available = self.available.lock().unwrap();
+        while *available == 0 {
+            available = self.cond.wait(available).unwrap();
+        }
+        *available -= 1;
+    }
+
+    fn release(&self) {
+        *self.available.lock().unwrap() += 1;
+        self.cond.notify_one();
+    }
+}
+
+/// Run `steps` concurrently, at most `jobs` at a time.
+///
+/// Each step acquires a token from a bounded pool before running and
+/// releases it when it finishes, so a `--jobs 1` run behaves like the old
+/// strictly sequential build: the first failure sets a shared `aborted`
+/// flag, and every step that hasn't started running yet - including, under
+/// `--jobs 1`, every step still queued behind the failed one - skips its
+/// turn instead of starting. A step already running when the flag flips is
+/// still let run to completion rather than interrupted mid-build: it's an
+/// opaque blocking `FnOnce` (CMake, cargo, PyInstaller...) with no
+/// cancellation point to interrupt it at. The first error wins and is
+/// returned tagged with the name of the step that produced it.
+pub fn run_parallel(jobs: usize, steps: Vec<Step>) -> Result<()> {
+    let pool = Arc::new(TokenPool::new(jobs));
+    let aborted = Arc::new(AtomicBool::new(false));
+    let (tx, rx) = mpsc::channel();
+
+    let handles: Vec<_> = steps
+        .into_iter()
+        .map(|(name, step)| {
+            let pool = pool.clone();
+            let tx = tx.clone();
+            let aborted = aborted.clone();
+            thread::spawn(move || {
+                // Skip before even contending for a token if an earlier
+                // step has already failed - the common case for steps
+                // still queued behind it.
+                if aborted.load(Ordering::SeqCst) {
+                    return;
+                }
+                pool.acquire();
+                // Re-check: a step could have failed while we were
+                // waiting for a token.
+                if aborted.load(Ordering::SeqCst) {
+                    pool.release();
+                    return;
+                }
+                // A step that panics (e.g. one of the many `.unwrap()`s in
+                // `ocr_sidecar`/`capture_sidecar`) must not just unwind this
+                // thread and drop `tx` silently - that would leave
+                // `first_err` at `None` below and report success.
+                let result = panic::catch_unwind(AssertUnwindSafe(step))
+                    .unwrap_or_else(|payload| Err(anyhow::anyhow!("panicked: {}", panic_message(&payload))));
+                pool.release();
+                if result.is_err() {
+                    aborted.store(true, Ordering::SeqCst);
+                }
+                let _ = tx.send((name, result));
+            })
+        })
+        .collect();
+
+    drop(tx);
+
+    let mut first_err = None;
+    for (name, result) in rx {
+        if let Err(e) = result {
+            if first_err.is_none() {
+                first_err = Some(e.context(format!("build step '{name}' failed")));
+            }
+        }
+    }
+
+    for handle in handles {
+        let _ = handle.join();
+    }
+
+    match first_err {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
+}
+
+/// Default job count: one per available CPU, falling back to 1.
+pub fn default_jobs() -> usize {
+    thread::available_parallelism().map(|n| n.get()).unwrap_or(1)
+}
+
+/// Best-effort extraction of a human-readable message from a
+/// `catch_unwind` payload - covers the common `panic!("...")` and
+/// `.unwrap()`/`.expect("...")` cases.
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
@@ -13,6 +13,7 @@
 
 mod ocr_sidecar;
 mod capture_sidecar;
+mod jobs;
 mod tauri;
 mod utils;
 mod qt;
@@ -31,13 +32,34 @@ struct Cli {
 #[derive(Subcommand)]
 enum Commands {
     /// Build everything (OCR sidecar + Capture Engine)
-    Build,
-    
+    Build {
+        /// Number of independent build steps to run concurrently
+        /// (defaults to available CPU parallelism)
+        #[arg(long)]
+        jobs: Option<usize>,
+
+        /// Cross-build for this target triple instead of the host
+        /// (e.g. `x86_64-unknown-linux-gnu`, `aarch64-apple-darwin`).
+        /// The OCR sidecar builds only for the host, so a foreign triple
+        /// fails that step.
+        #[arg(long)]
+        target: Option<String>,
+    },
+
     /// Build PaddleOCR sidecar executable
-    BuildOcr,
-    
+    BuildOcr {
+        /// Target triple; only the host triple is accepted, since the
+        /// PyInstaller build can't cross-compile
+        #[arg(long)]
+        target: Option<String>,
+    },
+
     /// Build Capture Engine (Qt + Rust wrapper)
-    BuildCapture,
+    BuildCapture {
+        /// Cross-build for this target triple instead of the host
+        #[arg(long)]
+        target: Option<String>,
+    },
     
     /// Build Qt native only (no Rust wrapper)
     BuildCaptureQt,
@@ -63,16 +85,34 @@ fn main() -> Result<()> {
     let cli = Cli::parse();
     
     match cli.command {
-        Commands::Build => {
-            ocr_sidecar::build()?;
-            capture_sidecar::build()?;
+        Commands::Build { jobs, target } => {
+            // OCR sidecar and capture engine builds are independent and both
+            // heavy (PyInstaller vs. CMake+cargo), so run them concurrently
+            // through a bounded job-token pool. Tauri packaging depends on
+            // both artifacts existing, so it still runs strictly after.
+            let jobs = jobs.unwrap_or_else(jobs::default_jobs);
+            let ocr_target = target.clone();
+            let capture_target = target.clone();
+            jobs::run_parallel(
+                jobs,
+                vec![
+                    (
+                        "ocr sidecar",
+                        Box::new(move || ocr_sidecar::build(ocr_target.as_deref())) as _,
+                    ),
+                    (
+                        "capture sidecar",
+                        Box::new(move || capture_sidecar::build(capture_target.as_deref())) as _,
+                    ),
+                ],
+            )?;
             tauri::build()?;
         }
-        Commands::BuildOcr => {
-            ocr_sidecar::build()?;
+        Commands::BuildOcr { target } => {
+            ocr_sidecar::build(target.as_deref())?;
         }
-        Commands::BuildCapture => {
-            capture_sidecar::build()?;
+        Commands::BuildCapture { target } => {
+            capture_sidecar::build(target.as_deref())?;
         }
         Commands::BuildCaptureQt => {
             capture_sidecar::build_qt_only()?;
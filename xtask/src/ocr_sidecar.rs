@@ -24,9 +24,25 @@ fn venv_python() -> std::path::PathBuf {
     }
 }
 
-pub fn build() -> Result<()> {
+pub fn build(target: Option<&str>) -> Result<()> {
     println!("\nBuilding PaddleOCR sidecar...");
 
+    // Unlike the Rust capture wrapper, PyInstaller bundles the host's own
+    // Python interpreter and native extensions - it cannot cross-compile.
+    // Silently relabeling a host build as `ocr-engine-<foreign-triple>`
+    // would ship a binary for the wrong platform with no indication
+    // anything's wrong, so refuse instead of faking it.
+    let host = target_triple();
+    if let Some(triple) = target {
+        if triple != host {
+            anyhow::bail!(
+                "PaddleOCR sidecar can't be cross-built: PyInstaller always \
+                 targets the host ({host}), so `--target {triple}` would \
+                 silently mislabel a {host} binary as {triple}"
+            );
+        }
+    }
+
     let sidecar = sidecar_dir();
     let venv = sidecar.join("venv");
 
@@ -99,10 +115,11 @@ pub fn build() -> Result<()> {
     )?;
 
     println!("\nCopying to Tauri binaries...");
+    // `target` is guaranteed equal to `host` by the cross-build refusal above.
     let binary_name = if cfg!(windows) {
-        format!("ocr-engine-{}.exe", target_triple())
+        format!("ocr-engine-{}.exe", host)
     } else {
-        format!("ocr-engine-{}", target_triple())
+        format!("ocr-engine-{}", host)
     };
     let dist_dir = sidecar.join("dist");
     let src_exe = if cfg!(windows) {